@@ -0,0 +1,248 @@
+use std::future::Future;
+use std::io::Result;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::AsyncBufRead;
+use futures::stream::Stream;
+
+/// Async mirror of `BufReadExt`, for types implementing `futures::io::AsyncBufRead` (tokio
+/// readers can be adapted with `tokio_util::compat`).
+pub trait AsyncBufReadExt: AsyncBufRead + Unpin + Sized {
+    /// Like `read_line_u8`, but for an `AsyncBufRead`.
+    fn read_line_u8<'a>(&'a mut self, buf: &'a mut Vec<u8>) -> ReadLineU8<'a, Self> {
+        ReadLineU8 {
+            inner: self,
+            buf,
+            read: 0,
+            dangling_cr: false,
+        }
+    }
+
+    fn lines_u8(self) -> LinesU8Stream<Self> {
+        LinesU8Stream {
+            inner: self,
+            dangling_cr: false,
+            line: vec![],
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufReadExt for R {}
+
+/// Future returned by `AsyncBufReadExt::read_line_u8`.
+pub struct ReadLineU8<'a, R: ?Sized> {
+    inner: &'a mut R,
+    buf: &'a mut Vec<u8>,
+    read: usize,
+    dangling_cr: bool,
+}
+
+impl<'a, R: AsyncBufRead + Unpin + ?Sized> Future for ReadLineU8<'a, R> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            match poll_one(Pin::new(&mut *this.inner), cx, this.buf, &mut this.dangling_cr) {
+                Poll::Ready(Ok((done, used))) => {
+                    this.read += used;
+                    if done {
+                        return Poll::Ready(Ok(this.read));
+                    }
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Stream returned by `AsyncBufReadExt::lines_u8`.
+pub struct LinesU8Stream<R> {
+    inner: R,
+    dangling_cr: bool,
+    // In-progress line, kept on `self` (rather than a local in `poll_next`) so a `Pending`
+    // partway through a line doesn't drop bytes already read on a previous poll.
+    line: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for LinesU8Stream<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            // A step that resolves a dangling `\r` ends a line, even an empty one with nothing
+            // consumed on this step, so it must not be taken for EOF.
+            let resolving_cr = this.dangling_cr;
+            match poll_one(Pin::new(&mut this.inner), cx, &mut this.line, &mut this.dangling_cr) {
+                Poll::Ready(Ok((done, used))) => {
+                    if done {
+                        return Poll::Ready(if used == 0 && this.line.is_empty() && !resolving_cr {
+                            None
+                        } else {
+                            Some(Ok(std::mem::take(&mut this.line)))
+                        });
+                    }
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// One step of the `read_line_u8` state machine, shared by the future and the stream. Returns
+/// `(done, bytes consumed this step)`. `dangling_cr` carries a trailing `\r` across poll calls
+/// (and therefore across `fill_buf` refills) so a `\r` at the end of one buffer and a `\n` at the
+/// start of the next still collapse into a single line ending.
+fn poll_one<R: AsyncBufRead + ?Sized>(
+    mut inner: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    buf: &mut Vec<u8>,
+    dangling_cr: &mut bool,
+) -> Poll<Result<(bool, usize)>> {
+    let available = match inner.as_mut().poll_fill_buf(cx) {
+        Poll::Ready(Ok(available)) => available,
+        Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+        Poll::Pending => return Poll::Pending,
+    };
+
+    if *dangling_cr {
+        *dangling_cr = false;
+        return if available.first() == Some(&b'\n') {
+            inner.consume(1);
+            Poll::Ready(Ok((true, 1)))
+        } else {
+            Poll::Ready(Ok((true, 0)))
+        };
+    }
+
+    if available.is_empty() {
+        return Poll::Ready(Ok((true, 0)));
+    }
+
+    let nl_idx = memchr::memchr(b'\n', available);
+    let cr_idx = memchr::memchr(b'\r', available);
+    let (done, used) = match (nl_idx, cr_idx) {
+        // \n
+        (Some(nl_idx), Some(cr_idx)) if nl_idx < cr_idx => {
+            buf.extend_from_slice(&available[..nl_idx]);
+            (true, nl_idx + 1)
+        }
+        (Some(nl_idx), None) => {
+            buf.extend_from_slice(&available[..nl_idx]);
+            (true, nl_idx + 1)
+        }
+        // \r\n
+        (Some(nl_idx), Some(cr_idx)) if cr_idx == nl_idx - 1 => {
+            buf.extend_from_slice(&available[..cr_idx]);
+            (true, nl_idx + 1)
+        }
+        // \r
+        (Some(_), Some(cr_idx)) => {
+            buf.extend_from_slice(&available[..cr_idx]);
+            (true, cr_idx + 1)
+        }
+        // trailing \r: defer the decision to the next poll, in case it starts with \n
+        (None, Some(cr_idx)) if cr_idx == available.len() - 1 => {
+            buf.extend_from_slice(&available[..cr_idx]);
+            *dangling_cr = true;
+            (false, cr_idx + 1)
+        }
+        (None, Some(cr_idx)) => {
+            buf.extend_from_slice(&available[..cr_idx]);
+            (true, cr_idx + 1)
+        }
+        (None, None) => {
+            buf.extend_from_slice(available);
+            (false, available.len())
+        }
+    };
+    inner.consume(used);
+    Poll::Ready(Ok((done, used)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::stream::StreamExt;
+
+    /// An `AsyncBufRead` that hands back one chunk per `poll_fill_buf` call, but returns
+    /// `Poll::Pending` on its very first call, so tests can exercise state carried across a
+    /// readiness gap partway through a line.
+    struct ChunkedReader {
+        chunks: std::collections::VecDeque<&'static [u8]>,
+        pos: usize,
+        polls_before_ready: usize,
+    }
+
+    impl ChunkedReader {
+        fn new(chunks: &[&'static [u8]]) -> Self {
+            ChunkedReader {
+                chunks: chunks.iter().copied().collect(),
+                pos: 0,
+                polls_before_ready: 1,
+            }
+        }
+    }
+
+    impl AsyncBufRead for ChunkedReader {
+        fn poll_fill_buf(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<&[u8]>> {
+            let this = self.get_mut();
+            if this.polls_before_ready > 0 {
+                this.polls_before_ready -= 1;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            match this.chunks.front() {
+                Some(chunk) => Poll::Ready(Ok(&chunk[this.pos..])),
+                None => Poll::Ready(Ok(&[])),
+            }
+        }
+
+        fn consume(self: Pin<&mut Self>, amt: usize) {
+            let this = self.get_mut();
+            this.pos += amt;
+            if let Some(chunk) = this.chunks.front() {
+                if this.pos >= chunk.len() {
+                    this.pos = 0;
+                    this.chunks.pop_front();
+                }
+            }
+        }
+    }
+
+    impl futures::io::AsyncRead for ChunkedReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<Result<usize>> {
+            unimplemented!("not exercised by these tests")
+        }
+    }
+
+    #[test]
+    fn lines_u8_stream_survives_pending_mid_line() {
+        // Reproduces a line spanning more than one readiness event: "abc" arrives, then
+        // `Pending`, then "def\n". The bytes read before the `Pending` must not be lost.
+        let reader = ChunkedReader::new(&[b"abc", b"def\n"]);
+        let lines: Vec<_> = block_on(reader.lines_u8().map(|r| r.unwrap()).collect());
+        assert_eq!(lines, vec![b"abcdef".to_vec()]);
+    }
+
+    #[test]
+    fn read_line_u8_future_survives_pending_mid_line() {
+        let mut reader = ChunkedReader::new(&[b"abc", b"def\n"]);
+        let mut line = vec![];
+        let read = block_on(reader.read_line_u8(&mut line)).unwrap();
+        assert_eq!(read, 7);
+        assert_eq!(line, b"abcdef");
+    }
+}
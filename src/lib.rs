@@ -1,4 +1,28 @@
+//! `no_std` environments get `BufRead` and friends from `core_io` instead of `std::io`, so they
+//! still need to pull in `alloc` for `Vec`. Disable the default `std` feature and enable the
+//! `core_io` feature to use this crate without `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+
+#[cfg(feature = "std")]
 use std::io::{ErrorKind, BufRead, Result};
+#[cfg(not(feature = "std"))]
+use core_io::{ErrorKind, BufRead, Result};
+
+// Async support needs an executor, so it only makes sense with `std`.
+#[cfg(all(feature = "std", feature = "futures-io"))]
+mod async_io;
+#[cfg(all(feature = "std", feature = "futures-io"))]
+pub use async_io::{AsyncBufReadExt, LinesU8Stream, ReadLineU8};
+
+mod line_reader;
+pub use line_reader::LineReaderU8;
 
 pub trait BufReadExt: BufRead + Sized {
     /// Like `read_until`, but fetches the next line, consumes \r, \n, or \r\n.
@@ -12,6 +36,29 @@ pub trait BufReadExt: BufRead + Sized {
     fn lines_u8(self) -> LinesIter<Self> {
         LinesIter { inner: self }
     }
+
+    /// Like `read_until`, but the terminator can be any multi-byte slice, not just a single
+    /// byte.
+    ///
+    /// The buf will not contain the delimiter, but the count will include its length, so if the
+    /// count is 0 we are at EOF.
+    fn read_until_slice(&mut self, delim: &[u8], buf: &mut Vec<u8>) -> Result<usize> {
+        read_until_slice(self, delim, buf)
+    }
+
+    fn split_slice(self, delim: Vec<u8>) -> SplitSliceIter<Self> {
+        SplitSliceIter { inner: self, delim }
+    }
+
+    /// Like `lines_u8`, but reuses one internal buffer across iterations instead of allocating a
+    /// fresh `Vec` per line, at the cost of borrowing rather than owning each line.
+    fn lines_u8_reuse(self) -> LinesReuseIter<Self> {
+        LinesReuseIter {
+            inner: self,
+            buf: Vec::new(),
+            pending_consume: 0,
+        }
+    }
 }
 
 impl<R: BufRead> BufReadExt for R {}
@@ -98,11 +145,264 @@ fn read_line_u8<R: BufRead + ?Sized>(r: &mut R, buf: &mut Vec<u8>) -> Result<usi
     }
 }
 
-#[cfg(test)]
+pub struct SplitSliceIter<R> {
+    inner: R,
+    delim: Vec<u8>,
+}
+
+impl<R> Iterator for SplitSliceIter<R>
+where R: BufRead
+{
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = vec![];
+        match self.inner.read_until_slice(&self.delim, &mut record) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(record)),
+            Err(e) => Some(Err(e))
+        }
+    }
+}
+
+fn read_until_slice<R: BufRead + ?Sized>(r: &mut R, delim: &[u8], buf: &mut Vec<u8>) -> Result<usize> {
+    let mut read = 0;
+    // A suffix of some earlier `fill_buf` that's a candidate prefix of `delim` (by construction,
+    // always equal to `delim[..held.len()]`), held back because we didn't yet have enough data
+    // to tell whether it's about to turn into a full match or is just ordinary content. Copied
+    // out to our own scratch space -- and consumed from `r` -- as soon as it's identified, since
+    // `BufRead` doesn't guarantee a later `fill_buf` returns anything new unless we do.
+    let mut held: Vec<u8> = Vec::new();
+    loop {
+        // Scanning (and acting on `buf`/`held`, but not calling `r.consume`) in its own block
+        // keeps the `fill_buf` borrow short-lived, so it doesn't conflict with `consume` below.
+        let (done, consumed) = {
+            let available = match r.fill_buf() {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            if available.is_empty() {
+                // EOF: whatever was held back never turned into a match, so it's trailing
+                // content.
+                buf.extend_from_slice(&held);
+                held.clear();
+                (true, 0)
+            } else if !held.is_empty() {
+                let held_len = held.len();
+                let need = delim.len() - held_len;
+                let take = need.min(available.len());
+                if available[..take] == delim[held_len..held_len + take] {
+                    held.extend_from_slice(&available[..take]);
+                    if held.len() == delim.len() {
+                        // `held` is now the full delimiter; none of it is content.
+                        held.clear();
+                        (true, take)
+                    } else {
+                        // Still incomplete because `available` ran dry first; loop for more data.
+                        (false, take)
+                    }
+                } else {
+                    // The next bytes don't continue the match, so no delimiter occurrence starts
+                    // at `held[0]`: it's real content. A shorter suffix of `held` may still start
+                    // one, so keep the longest suffix that's a prefix of `delim`. Nothing in
+                    // `available` has been consumed yet, so the next iteration re-scans it.
+                    loop {
+                        buf.push(held.remove(0));
+                        if delim.starts_with(&held) {
+                            break;
+                        }
+                    }
+                    (false, 0)
+                }
+            } else if let Some(idx) = find_slice(available, delim) {
+                buf.extend_from_slice(&available[..idx]);
+                (true, idx + delim.len())
+            } else {
+                // No full match in the buffer we have so far. A suffix of `available` might be
+                // a prefix of `delim` that's about to be completed by the next `fill_buf`; copy
+                // it into `held` and consume all of `available`, so the next loop iteration is
+                // guaranteed a fresh `fill_buf` rather than the same slice again.
+                let held_back = partial_match_len(available, delim);
+                let used = available.len() - held_back;
+                buf.extend_from_slice(&available[..used]);
+                held.extend_from_slice(&available[used..]);
+                (false, available.len())
+            }
+        };
+        r.consume(consumed);
+        read += consumed;
+        if done {
+            return Ok(read);
+        }
+    }
+}
+
+/// Find the first full occurrence of `delim` in `haystack`.
+fn find_slice(haystack: &[u8], delim: &[u8]) -> Option<usize> {
+    if delim.is_empty() {
+        return Some(0);
+    }
+    haystack.windows(delim.len()).position(|w| w == delim)
+}
+
+/// The length of the longest suffix of `haystack` that is a proper prefix of `delim`.
+fn partial_match_len(haystack: &[u8], delim: &[u8]) -> usize {
+    let max = delim.len().saturating_sub(1).min(haystack.len());
+    (1..=max)
+        .rev()
+        .find(|&len| haystack[haystack.len() - len..] == delim[..len])
+        .unwrap_or(0)
+}
+
+pub struct LinesReuseIter<R> {
+    inner: R,
+    buf: Vec<u8>,
+    /// Bytes to `consume` from `inner` before the next `fill_buf`, deferred from the previous
+    /// call so a zero-copy line borrowed straight out of `inner`'s buffer can still be returned.
+    pending_consume: usize,
+}
+
+impl<R: BufRead> LinesReuseIter<R> {
+    // This can't be a real `Iterator` impl: `Item` would need to borrow from `self`, which
+    // isn't expressible without generic associated types.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Result<&[u8]>> {
+        if self.pending_consume > 0 {
+            self.inner.consume(self.pending_consume);
+            self.pending_consume = 0;
+        }
+        self.buf.clear();
+        // Set when the previous loop iteration ended on a trailing `\r`: whether it terminates
+        // the line alone or as part of `\r\n` is deferred to this iteration's `fill_buf`, the
+        // same way `dangling_cr` works in `async_io` and `LineReaderU8`.
+        let mut dangling_cr = false;
+        loop {
+            if dangling_cr {
+                let available = match self.inner.fill_buf() {
+                    Ok(n) => n,
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Some(Err(e)),
+                };
+                if available.first() == Some(&b'\n') {
+                    self.inner.consume(1);
+                }
+                // Reaching here at all means a `\r` terminator was found earlier in this call,
+                // so this is a real line -- possibly empty, but not "no more lines" (that's
+                // decided by the ordinary scan below, before any `\r` is seen).
+                return Some(Ok(&self.buf[..]));
+            }
+
+            // Scanning (but not acting on) `fill_buf`'s result in its own block keeps this
+            // borrow short-lived, so it doesn't conflict with the `consume`/re-`fill_buf` calls
+            // below: the borrow checker can't otherwise tell that the two `fill_buf` calls
+            // return the same data without an intervening `consume`.
+            let (done, line_end, consumed, defer_cr) = {
+                let available = match self.inner.fill_buf() {
+                    Ok(n) => n,
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Some(Err(e)),
+                };
+                scan_terminator(available)
+            };
+
+            if done && !defer_cr && self.buf.is_empty() {
+                // The whole line, terminator included, lived in this one `fill_buf` call, so it
+                // can be returned as-is with no copy. `consume` is deferred to the start of the
+                // next call, since calling it here would conflict with borrowing `available` for
+                // the return.
+                self.pending_consume = consumed;
+                let available = self.inner.fill_buf().expect("fill_buf without consume repeats");
+                return Some(Ok(&available[..line_end]));
+            }
+
+            {
+                let available = self.inner.fill_buf().expect("fill_buf without consume repeats");
+                self.buf.extend_from_slice(&available[..line_end]);
+            }
+            self.inner.consume(consumed);
+            if defer_cr {
+                dangling_cr = true;
+                continue;
+            }
+            if done || consumed == 0 {
+                return if self.buf.is_empty() {
+                    None
+                } else {
+                    Some(Ok(&self.buf[..]))
+                };
+            }
+        }
+    }
+}
+
+/// Find where the current line ends in `available`, per the same `\r` / `\n` / `\r\n` rules as
+/// `read_line_u8`. Returns `(line complete, content length, bytes to consume, trailing \r
+/// deferred)`. When the last byte scanned is `\r` with no more of `available` to check, whether
+/// it's a lone `\r` or the start of `\r\n` can't be decided yet: the content length stops just
+/// before that `\r`, the bytes to consume include it, `done` is `false`, and the deferred flag tells
+/// the caller to resolve it against the next `fill_buf` instead of treating it as EOF-like.
+fn scan_terminator(available: &[u8]) -> (bool, usize, usize, bool) {
+    let nl_idx = memchr::memchr(b'\n', available);
+    let cr_idx = memchr::memchr(b'\r', available);
+    match (nl_idx, cr_idx) {
+        // \n
+        (Some(nl_idx), Some(cr_idx)) if nl_idx < cr_idx => (true, nl_idx, nl_idx + 1, false),
+        (Some(nl_idx), None) => (true, nl_idx, nl_idx + 1, false),
+        // \r\n
+        (Some(nl_idx), Some(cr_idx)) if cr_idx == nl_idx - 1 => (true, cr_idx, nl_idx + 1, false),
+        // \r
+        (Some(_), Some(cr_idx)) => (true, cr_idx, cr_idx + 1, false),
+        // trailing \r: defer the decision to the next fill_buf, in case it starts with \n
+        (None, Some(cr_idx)) if cr_idx == available.len() - 1 => {
+            (false, cr_idx, cr_idx + 1, true)
+        }
+        (None, Some(cr_idx)) => (true, cr_idx, cr_idx + 1, false),
+        _ => (false, available.len(), available.len(), false),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
-    use std::io::Cursor;
+    use std::io::{BufRead, Cursor};
     use super::BufReadExt;
 
+    /// A `BufRead` that only ever hands back `chunk_size` bytes per `fill_buf` call, so tests can
+    /// force a terminator or delimiter match to straddle a buffer boundary -- something a
+    /// `Cursor`, which always returns all remaining input in one `fill_buf`, can never exercise.
+    struct SmallReadsReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+        chunk_size: usize,
+    }
+
+    impl<'a> SmallReadsReader<'a> {
+        fn new(data: &'a [u8], chunk_size: usize) -> Self {
+            SmallReadsReader { data, pos: 0, chunk_size }
+        }
+    }
+
+    impl<'a> std::io::Read for SmallReadsReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            self.consume(n);
+            Ok(n)
+        }
+    }
+
+    impl<'a> std::io::BufRead for SmallReadsReader<'a> {
+        fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+            let end = (self.pos + self.chunk_size).min(self.data.len());
+            Ok(&self.data[self.pos..end])
+        }
+        fn consume(&mut self, amt: usize) {
+            self.pos += amt;
+        }
+    }
+
     #[test]
     fn read_line_u8() {
         let mut text = Cursor::new("Some\r text\r\n\n\r");
@@ -132,4 +432,79 @@ mod tests {
         assert_eq!(iter.next().unwrap().unwrap(), b"");
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn read_until_slice() {
+        let mut text = Cursor::new("one::two::three");
+        let mut record = vec![];
+        assert_eq!(text.read_until_slice(b"::", &mut record).unwrap(), 5);
+        assert_eq!(record, b"one");
+        record.clear();
+        assert_eq!(text.read_until_slice(b"::", &mut record).unwrap(), 5);
+        assert_eq!(record, b"two");
+        record.clear();
+        assert_eq!(text.read_until_slice(b"::", &mut record).unwrap(), 5);
+        assert_eq!(record, b"three");
+        record.clear();
+        assert_eq!(text.read_until_slice(b"::", &mut record).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_until_slice_across_small_reads() {
+        // A reader that only ever hands back one byte at a time forces every delimiter match to
+        // straddle a `fill_buf` boundary, exercising the partial-match-holdback path.
+        let mut reader = SmallReadsReader::new(b"one::two::three", 1);
+        let mut record = vec![];
+        assert_eq!(reader.read_until_slice(b"::", &mut record).unwrap(), 5);
+        assert_eq!(record, b"one");
+        record.clear();
+        assert_eq!(reader.read_until_slice(b"::", &mut record).unwrap(), 5);
+        assert_eq!(record, b"two");
+        record.clear();
+        assert_eq!(reader.read_until_slice(b"::", &mut record).unwrap(), 5);
+        assert_eq!(record, b"three");
+        record.clear();
+        assert_eq!(reader.read_until_slice(b"::", &mut record).unwrap(), 0);
+    }
+
+    #[test]
+    fn split_slice() {
+        let text = Cursor::new("one::two::three");
+        let mut iter = text.split_slice(b"::".to_vec());
+        assert_eq!(iter.next().unwrap().unwrap(), b"one");
+        assert_eq!(iter.next().unwrap().unwrap(), b"two");
+        assert_eq!(iter.next().unwrap().unwrap(), b"three");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn lines_u8_reuse() {
+        let text = Cursor::new("Some\r text\r\n\n\r");
+        let mut iter = text.lines_u8_reuse();
+        assert_eq!(iter.next().unwrap().unwrap(), b"Some");
+        assert_eq!(iter.next().unwrap().unwrap(), b" text");
+        assert_eq!(iter.next().unwrap().unwrap(), b"");
+        assert_eq!(iter.next().unwrap().unwrap(), b"");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn lines_u8_reuse_across_small_reads() {
+        // A reader that only ever hands back one byte at a time forces every line to span
+        // multiple `fill_buf` calls, exercising the scratch-buffer fallback path.
+        let mut iter = SmallReadsReader::new(b"foo\nbar\n", 1).lines_u8_reuse();
+        assert_eq!(iter.next().unwrap().unwrap(), b"foo");
+        assert_eq!(iter.next().unwrap().unwrap(), b"bar");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn lines_u8_reuse_trailing_cr_split_from_newline() {
+        // A reader handing back 2 bytes per `fill_buf` call splits the "\r\n" in "a\r\nb" across
+        // a buffer boundary, exercising the deferred-`\r` path in `scan_terminator`.
+        let mut iter = SmallReadsReader::new(b"a\r\nb", 2).lines_u8_reuse();
+        assert_eq!(iter.next().unwrap().unwrap(), b"a");
+        assert_eq!(iter.next().unwrap().unwrap(), b"b");
+        assert!(iter.next().is_none());
+    }
 }
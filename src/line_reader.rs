@@ -0,0 +1,308 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+/// A line reader for callers who receive input in arbitrary pushed chunks (network packets,
+/// async callbacks, ...) and so can't wrap something implementing `BufRead`.
+///
+/// Uses the same `\r` / `\n` / `\r\n` rules as `read_line_u8`. A line entirely contained in one
+/// pushed chunk is returned without copying, unless it ends in a `\r` that is the last byte of
+/// its chunk; such a line, and a line spanning more than one chunk, is coalesced into an
+/// internal scratch buffer instead.
+pub struct LineReaderU8<T> {
+    bufs: VecDeque<T>,
+    /// Start of the current (possibly partial) line within the front buffer. Once a line spans
+    /// buffers, its bytes from earlier buffers live in `scratch` and this is the start of the
+    /// rest of it in the front buffer.
+    line_start: usize,
+    /// How far the front buffer has been scanned for a terminator without finding one, so a
+    /// re-scan after `push` doesn't re-examine those bytes.
+    search_pos: usize,
+    scratch: Vec<u8>,
+    /// The previous scan ended on a `\r`; still waiting to see if a `\n` follows in the next
+    /// pushed chunk.
+    dangling_cr: bool,
+    /// `finish` was called: once the input runs dry, flush a trailing unterminated line.
+    finished: bool,
+    /// The last `next_line` returned a slice of `scratch`; clear it before scanning further.
+    returned_via_scratch: bool,
+}
+
+impl<T: AsRef<[u8]>> LineReaderU8<T> {
+    pub fn new() -> Self {
+        LineReaderU8 {
+            bufs: VecDeque::new(),
+            line_start: 0,
+            search_pos: 0,
+            scratch: Vec::new(),
+            dangling_cr: false,
+            finished: false,
+            returned_via_scratch: false,
+        }
+    }
+
+    /// Feed another chunk of input in.
+    pub fn push(&mut self, chunk: T) {
+        self.bufs.push_back(chunk);
+    }
+
+    /// Signal that no more input is coming, so a final unterminated line, if any, is flushed by
+    /// the next call to `next_line`.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Return the next complete line, or `None` if none is available yet (call `push` and try
+    /// again) or the input is exhausted.
+    pub fn next_line(&mut self) -> Option<&[u8]> {
+        if self.returned_via_scratch {
+            self.scratch.clear();
+            self.returned_via_scratch = false;
+        }
+
+        if self.dangling_cr {
+            return self.resolve_dangling_cr();
+        }
+
+        loop {
+            // Drop front buffers whose bytes have all been consumed by lines already returned.
+            while let Some(front_len) = self.bufs.front().map(|b| b.as_ref().len()) {
+                if self.scratch.is_empty() && self.line_start >= front_len {
+                    self.pop_front();
+                } else {
+                    break;
+                }
+            }
+
+            let front_len = match self.bufs.front() {
+                Some(b) => b.as_ref().len(),
+                None => return self.flush_on_finish(),
+            };
+            let (nl_idx, cr_idx) = {
+                let front = self.bufs.front().unwrap().as_ref();
+                let available = &front[self.search_pos..];
+                (
+                    memchr::memchr(b'\n', available),
+                    memchr::memchr(b'\r', available),
+                )
+            };
+            match (nl_idx, cr_idx) {
+                // \n
+                (Some(nl), Some(cr)) if nl < cr => {
+                    return Some(self.finalize(self.search_pos + nl, 1));
+                }
+                (Some(nl), None) => return Some(self.finalize(self.search_pos + nl, 1)),
+                // \r\n
+                (Some(nl), Some(cr)) if cr == nl - 1 => {
+                    return Some(self.finalize(self.search_pos + cr, 2));
+                }
+                // \r, not at the end of what we have
+                (Some(_), Some(cr)) => return Some(self.finalize(self.search_pos + cr, 1)),
+                (None, Some(cr)) if self.search_pos + cr == front_len - 1 => {
+                    // Trailing `\r`: could be about to become `\r\n` once more data arrives.
+                    self.dangling_cr = true;
+                    self.search_pos = front_len;
+                    return self.resolve_dangling_cr();
+                }
+                (None, Some(cr)) => return Some(self.finalize(self.search_pos + cr, 1)),
+                (None, None) => {
+                    if self.bufs.len() >= 2 {
+                        // This chunk has no terminator at all; the line must continue in the
+                        // next one, so coalesce what we have and keep scanning.
+                        self.spill_to_scratch();
+                        continue;
+                    }
+                    self.search_pos = front_len;
+                    return self.flush_on_finish();
+                }
+            }
+        }
+    }
+
+    fn pop_front(&mut self) {
+        self.bufs.pop_front();
+        self.line_start = 0;
+        self.search_pos = 0;
+    }
+
+    /// Move the front buffer's part of the current line (from `line_start` on) into `scratch`,
+    /// then drop the buffer: the current line now spans more than one pushed chunk.
+    fn spill_to_scratch(&mut self) {
+        if let Some(front) = self.bufs.front() {
+            self.scratch.extend_from_slice(&front.as_ref()[self.line_start..]);
+        }
+        self.pop_front();
+    }
+
+    /// Advance past `n` bytes of the current line (content already accounted for), crossing
+    /// into later buffers if needed, but never dropping the buffer that a still-live zero-copy
+    /// return might be borrowed from.
+    fn advance(&mut self, mut n: usize) {
+        loop {
+            let front_len = match self.bufs.front() {
+                Some(b) => b.as_ref().len(),
+                None => return,
+            };
+            let remaining = front_len - self.line_start;
+            if n <= remaining {
+                self.line_start += n;
+                self.search_pos = self.line_start;
+                return;
+            }
+            n -= remaining;
+            self.pop_front();
+        }
+    }
+
+    /// Finalize a line whose content ends at absolute offset `end` in the front buffer, with a
+    /// `term_len`-byte terminator immediately after it.
+    fn finalize(&mut self, end: usize, term_len: usize) -> &[u8] {
+        let start = self.line_start;
+        if self.scratch.is_empty() {
+            self.advance(end - start + term_len);
+            &self.bufs.front().expect("buffer consumed during finalize").as_ref()[start..end]
+        } else {
+            if let Some(front) = self.bufs.front() {
+                self.scratch.extend_from_slice(&front.as_ref()[start..end]);
+            }
+            self.advance(end - start + term_len);
+            self.returned_via_scratch = true;
+            &self.scratch[..]
+        }
+    }
+
+    /// Same as `finalize`, but the trailing `\r` being consumed was the last byte available when
+    /// it was found, so there's no guarantee the source buffer survives consuming the
+    /// terminator (it may need to reach into the next chunk for the `\n`): always go through
+    /// `scratch`.
+    fn finalize_spanning(&mut self, cr_end: usize, consume_next_byte: bool) -> &[u8] {
+        let start = self.line_start;
+        if let Some(front) = self.bufs.front() {
+            self.scratch.extend_from_slice(&front.as_ref()[start..cr_end]);
+        }
+        self.advance(cr_end - start + 1);
+        if consume_next_byte {
+            self.advance(1);
+        }
+        self.returned_via_scratch = true;
+        &self.scratch[..]
+    }
+
+    fn resolve_dangling_cr(&mut self) -> Option<&[u8]> {
+        let front_len = match self.bufs.front() {
+            Some(b) => b.as_ref().len(),
+            None => {
+                self.dangling_cr = false;
+                return None;
+            }
+        };
+        let cr_end = front_len - 1;
+        // Skip past any empty pushed chunks after the one with the trailing `\r`: they carry no
+        // information either way about whether a `\n` follows, so don't let one make us treat
+        // the `\r` as a lone terminator.
+        let mut idx = 1;
+        while self.bufs.get(idx).map(|b| b.as_ref().is_empty()) == Some(true) {
+            idx += 1;
+        }
+        match self.bufs.get(idx) {
+            Some(next) => {
+                self.dangling_cr = false;
+                let next_is_nl = next.as_ref().first() == Some(&b'\n');
+                Some(self.finalize_spanning(cr_end, next_is_nl))
+            }
+            None if self.finished => {
+                self.dangling_cr = false;
+                Some(self.finalize_spanning(cr_end, false))
+            }
+            None => None,
+        }
+    }
+
+    /// If `finish` was called and there's a trailing unterminated line, return it once.
+    fn flush_on_finish(&mut self) -> Option<&[u8]> {
+        if !self.finished {
+            return None;
+        }
+        let end = self.bufs.front().map(|b| b.as_ref().len()).unwrap_or(self.line_start);
+        if self.scratch.is_empty() && end == self.line_start {
+            return None;
+        }
+        Some(self.finalize(end, 0))
+    }
+}
+
+impl<T: AsRef<[u8]>> Default for LineReaderU8<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::LineReaderU8;
+
+    #[test]
+    fn single_chunk() {
+        let mut r = LineReaderU8::new();
+        r.push(&b"Some\r text\r\n\n\r"[..]);
+        assert_eq!(r.next_line().unwrap(), b"Some");
+        assert_eq!(r.next_line().unwrap(), b" text");
+        assert_eq!(r.next_line().unwrap(), b"");
+        assert_eq!(r.next_line(), None);
+        r.finish();
+        assert_eq!(r.next_line().unwrap(), b"");
+        assert_eq!(r.next_line(), None);
+    }
+
+    #[test]
+    fn line_split_across_chunks() {
+        let mut r = LineReaderU8::new();
+        r.push(&b"foo"[..]);
+        assert_eq!(r.next_line(), None);
+        r.push(&b"bar\nbaz"[..]);
+        assert_eq!(r.next_line().unwrap(), b"foobar");
+        assert_eq!(r.next_line(), None);
+        r.finish();
+        assert_eq!(r.next_line().unwrap(), b"baz");
+        assert_eq!(r.next_line(), None);
+    }
+
+    #[test]
+    fn crlf_split_across_chunks() {
+        let mut r = LineReaderU8::new();
+        r.push(&b"foo\r"[..]);
+        assert_eq!(r.next_line(), None);
+        r.push(&b"\nbar"[..]);
+        assert_eq!(r.next_line().unwrap(), b"foo");
+        r.finish();
+        assert_eq!(r.next_line().unwrap(), b"bar");
+        assert_eq!(r.next_line(), None);
+    }
+
+    #[test]
+    fn crlf_split_across_chunks_with_empty_chunk_between() {
+        let mut r = LineReaderU8::new();
+        r.push(&b"foo\r"[..]);
+        assert_eq!(r.next_line(), None);
+        r.push(&b""[..]);
+        assert_eq!(r.next_line(), None);
+        r.push(&b"\nbar"[..]);
+        assert_eq!(r.next_line().unwrap(), b"foo");
+        r.finish();
+        assert_eq!(r.next_line().unwrap(), b"bar");
+        assert_eq!(r.next_line(), None);
+    }
+
+    #[test]
+    fn lone_cr_at_pending_eof() {
+        let mut r = LineReaderU8::new();
+        r.push(&b"foo\r"[..]);
+        assert_eq!(r.next_line(), None);
+        r.finish();
+        assert_eq!(r.next_line().unwrap(), b"foo");
+        assert_eq!(r.next_line(), None);
+    }
+}